@@ -1,57 +1,520 @@
 use crate::{MatchSpec, PackageRecord, Range, Version};
 use itertools::Itertools;
 use pubgrub::version_set::VersionSet;
-use smallvec::SmallVec;
-use std::collections::hash_map::DefaultHasher;
-use std::collections::HashSet;
+use smallvec::{smallvec, SmallVec};
+use std::collections::BTreeSet;
+use std::error::Error;
 use std::fmt::{Display, Formatter};
-use std::hash::{Hash, Hasher};
 use std::iter::once;
+use std::str::FromStr;
+
+/// An error returned when a textual constraint produced by `MatchSpecConstraints`'s
+/// `Display` impl can't be parsed back by `FromStr`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseMatchSpecConstraintsError(String);
+
+impl Display for ParseMatchSpecConstraintsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid match spec constraint: {}", self.0)
+    }
+}
+
+impl Error for ParseMatchSpecConstraintsError {}
+
+impl From<String> for ParseMatchSpecConstraintsError {
+    fn from(message: String) -> Self {
+        Self(message)
+    }
+}
+
+/// A single glob-style build-string predicate, e.g. `py37*` or `*cuda*`.
+///
+/// Only `*` is treated as a wildcard (matching any run of characters); every other
+/// character is matched literally, mirroring the build-string globs conda itself
+/// accepts in a `MatchSpec`.
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+struct BuildGlob(String);
+
+impl BuildGlob {
+    fn new(pattern: impl Into<String>) -> Self {
+        Self(pattern.into())
+    }
+
+    /// Returns true if `build` matches this glob pattern.
+    fn matches(&self, build: &str) -> bool {
+        glob_match(self.0.as_bytes(), build.as_bytes())
+    }
+}
+
+impl Display for BuildGlob {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Classic `*`-only glob matcher, used to evaluate build-string patterns.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => {
+            glob_match(rest, text) || (!text.is_empty() && glob_match(pattern, &text[1..]))
+        }
+        Some((&c, rest)) => {
+            matches!(text.split_first(), Some((&t, tail)) if t == c && glob_match(rest, tail))
+        }
+    }
+}
+
+/// Returns true if some build string could match both glob patterns at once, i.e.
+/// whether their languages intersect. Walks both patterns in lockstep, letting a `*`
+/// either end (matching zero more characters) or absorb one character of whatever the
+/// other pattern requires next, so it never has to materialize a witness string.
+fn globs_compatible(a: &[u8], b: &[u8]) -> bool {
+    match (a.split_first(), b.split_first()) {
+        (None, None) => true,
+        (None, Some((&b'*', rest))) => globs_compatible(a, rest),
+        (Some((&b'*', rest)), None) => globs_compatible(rest, b),
+        (None, Some(_)) | (Some(_), None) => false,
+        (Some((&b'*', a_rest)), Some((&b'*', b_rest))) => {
+            globs_compatible(a_rest, b) || globs_compatible(a, b_rest)
+        }
+        (Some((&b'*', a_rest)), Some(_)) => {
+            globs_compatible(a_rest, b) || globs_compatible(a, &b[1..])
+        }
+        (Some(_), Some((&b'*', b_rest))) => {
+            globs_compatible(&a[1..], b) || globs_compatible(a, b_rest)
+        }
+        (Some((&ac, a_rest)), Some((&bc, b_rest))) => ac == bc && globs_compatible(a_rest, b_rest),
+    }
+}
+
+/// A constraint on a package's build string.
+///
+/// Glob sets aren't generally closed under complement, so instead of modeling this as
+/// an arbitrary set of strings we keep it as one clause of a DNF: a conjunction of
+/// positive literals (the build string must match every glob in `positive`) and
+/// negative literals (the build string must match none of the globs in `negative`).
+/// `MatchSpecConstraints::complement` fans this clause back out into the top-level DNF by
+/// negating one literal at a time, the same way it already does for the version and
+/// build-number dimensions.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+enum BuildStringSet {
+    /// Matches any build string.
+    Any,
+    /// Matches no build string.
+    None,
+    /// Build string must match every glob in `positive` and none of the globs in `negative`.
+    Constrained {
+        positive: BTreeSet<BuildGlob>,
+        negative: BTreeSet<BuildGlob>,
+    },
+}
+
+impl BuildStringSet {
+    fn any() -> Self {
+        Self::Any
+    }
+
+    fn none() -> Self {
+        Self::None
+    }
+
+    /// Builds a `Constrained` set, collapsing to `None`/`Any` when the literals make that
+    /// trivially true: a glob required to both match and not match is unsatisfiable, as
+    /// is a set of positive globs whose languages don't all overlap (e.g. `py37*` and
+    /// `py38*`); no literals at all matches everything. Only *pairwise* incompatibility
+    /// between positive globs is checked — an unsatisfiable combination that only
+    /// emerges from three or more globs together is not detected and would surface as a
+    /// non-canonical, always-empty `Constrained` set instead of `None`.
+    fn literal(positive: BTreeSet<BuildGlob>, negative: BTreeSet<BuildGlob>) -> Self {
+        if positive.intersection(&negative).next().is_some() {
+            return Self::None;
+        }
+        if positive
+            .iter()
+            .tuple_combinations()
+            .any(|(a, b): (&BuildGlob, &BuildGlob)| !globs_compatible(a.0.as_bytes(), b.0.as_bytes()))
+        {
+            return Self::None;
+        }
+        if positive.is_empty() && negative.is_empty() {
+            Self::Any
+        } else {
+            Self::Constrained { positive, negative }
+        }
+    }
+
+    fn single_positive(glob: BuildGlob) -> Self {
+        Self::literal(once(glob).collect(), BTreeSet::new())
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::None, _) | (_, Self::None) => Self::None,
+            (Self::Any, other) => other.clone(),
+            (this, Self::Any) => this.clone(),
+            (
+                Self::Constrained {
+                    positive: p1,
+                    negative: n1,
+                },
+                Self::Constrained {
+                    positive: p2,
+                    negative: n2,
+                },
+            ) => Self::literal(p1.union(p2).cloned().collect(), n1.union(n2).cloned().collect()),
+        }
+    }
+
+    fn contains(&self, build: &str) -> bool {
+        match self {
+            Self::Any => true,
+            Self::None => false,
+            Self::Constrained { positive, negative } => {
+                positive.iter().all(|glob| glob.matches(build))
+                    && !negative.iter().any(|glob| glob.matches(build))
+            }
+        }
+    }
+
+    /// Returns the complement of this constraint, expressed as the individual DNF terms
+    /// that together form its negation (one term per literal this constraint requires).
+    fn negated_terms(&self) -> SmallVec<[Self; 2]> {
+        match self {
+            Self::Any => SmallVec::new(),
+            Self::None => smallvec![Self::Any],
+            Self::Constrained { positive, negative } => positive
+                .iter()
+                .map(|glob| Self::literal(BTreeSet::new(), once(glob.clone()).collect()))
+                .chain(negative.iter().map(|glob| Self::single_positive(glob.clone())))
+                .collect(),
+        }
+    }
+}
+
+impl Display for BuildStringSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Any => write!(f, "*"),
+            Self::None => write!(f, "!"),
+            Self::Constrained { positive, negative } => {
+                let mut literals = positive
+                    .iter()
+                    .map(ToString::to_string)
+                    .chain(negative.iter().map(|glob| format!("!{glob}")));
+                write!(f, "{}", literals.join(","))
+            }
+        }
+    }
+}
+
+impl FromStr for BuildStringSet {
+    type Err = ParseMatchSpecConstraintsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "*" {
+            return Ok(Self::Any);
+        }
+        if s == "!" {
+            return Ok(Self::None);
+        }
+
+        let mut positive = BTreeSet::new();
+        let mut negative = BTreeSet::new();
+        for literal in s.split(',') {
+            match literal.strip_prefix('!') {
+                Some(glob) => {
+                    negative.insert(BuildGlob::new(glob));
+                }
+                None => {
+                    positive.insert(BuildGlob::new(literal));
+                }
+            }
+        }
+        Ok(Self::literal(positive, negative))
+    }
+}
+
+/// A constraint on a package's name.
+///
+/// A `MatchSpec` always names exactly one package, so unlike the build-string
+/// dimension this never needs a general literal set: `positive` holds at most the one
+/// required name, and `negative` holds names the package must *not* have (produced
+/// when `complement` negates a `positive` requirement). This is what lets a
+/// `MatchSpecElement` for `python` and one for `openssl` intersect to `none()` instead
+/// of being conflated.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+enum NameConstraint {
+    /// Matches a package with any name.
+    Any,
+    /// Matches no package.
+    None,
+    /// Package name must equal `positive` (if set) and must not equal any of `negative`.
+    Constrained {
+        positive: Option<String>,
+        negative: BTreeSet<String>,
+    },
+}
+
+impl NameConstraint {
+    fn any() -> Self {
+        Self::Any
+    }
+
+    fn none() -> Self {
+        Self::None
+    }
+
+    fn literal(positive: Option<String>, negative: BTreeSet<String>) -> Self {
+        if let Some(name) = &positive {
+            if negative.contains(name) {
+                return Self::None;
+            }
+            // Once `positive` pins the name, `negative` can never also match, so it
+            // carries no information and must not be kept around: retaining it would
+            // let the same set exist in two structural forms, breaking canonicalization
+            // and making `Display` lossy.
+            return Self::Constrained {
+                positive: Some(name.clone()),
+                negative: BTreeSet::new(),
+            };
+        }
+        if negative.is_empty() {
+            Self::Any
+        } else {
+            Self::Constrained { positive, negative }
+        }
+    }
+
+    fn single_positive(name: String) -> Self {
+        Self::literal(Some(name), BTreeSet::new())
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::None, _) | (_, Self::None) => Self::None,
+            (Self::Any, other) => other.clone(),
+            (this, Self::Any) => this.clone(),
+            (
+                Self::Constrained {
+                    positive: p1,
+                    negative: n1,
+                },
+                Self::Constrained {
+                    positive: p2,
+                    negative: n2,
+                },
+            ) => {
+                let positive = match (p1, p2) {
+                    (Some(a), Some(b)) if a != b => return Self::None,
+                    (Some(a), _) | (_, Some(a)) => Some(a.clone()),
+                    (None, None) => None,
+                };
+                Self::literal(positive, n1.union(n2).cloned().collect())
+            }
+        }
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        match self {
+            Self::Any => true,
+            Self::None => false,
+            Self::Constrained { positive, negative } => {
+                positive.as_deref().map_or(true, |p| p == name)
+                    && !negative.iter().any(|n| n == name)
+            }
+        }
+    }
+
+    /// Returns the complement of this constraint, expressed as the individual DNF terms
+    /// that together form its negation.
+    fn negated_terms(&self) -> SmallVec<[Self; 2]> {
+        match self {
+            Self::Any => SmallVec::new(),
+            Self::None => smallvec![Self::Any],
+            Self::Constrained { positive, negative } => positive
+                .iter()
+                .map(|name| Self::literal(None, once(name.clone()).collect()))
+                .chain(negative.iter().map(|name| Self::single_positive(name.clone())))
+                .collect(),
+        }
+    }
+}
+
+impl Display for NameConstraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Any => write!(f, "*"),
+            Self::None => write!(f, "!"),
+            Self::Constrained {
+                positive: Some(name),
+                ..
+            } => write!(f, "{name}"),
+            Self::Constrained {
+                positive: None,
+                negative,
+            } => write!(f, "!({})", negative.iter().join(",")),
+        }
+    }
+}
+
+impl FromStr for NameConstraint {
+    type Err = ParseMatchSpecConstraintsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "*" {
+            Ok(Self::Any)
+        } else if s == "!" {
+            Ok(Self::None)
+        } else if let Some(excluded) = s.strip_prefix("!(").and_then(|s| s.strip_suffix(')')) {
+            Ok(Self::literal(
+                None,
+                excluded.split(',').map(str::to_string).collect(),
+            ))
+        } else {
+            Ok(Self::single_positive(s.to_string()))
+        }
+    }
+}
 
 /// A single AND group in a `MatchSpecConstraints`
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct MatchSpecElement {
+    name: NameConstraint,
     version: Range<Version>,
     build_number: Range<usize>,
+    build: BuildStringSet,
 }
 
 impl MatchSpecElement {
     /// Returns an instance that matches nothing.
     fn none() -> Self {
         Self {
+            name: NameConstraint::none(),
             version: Range::none(),
             build_number: Range::none(),
+            build: BuildStringSet::none(),
         }
     }
 
     /// Returns an instance that matches anything.
     fn any() -> Self {
         Self {
+            name: NameConstraint::any(),
             version: Range::any(),
             build_number: Range::any(),
+            build: BuildStringSet::any(),
         }
     }
 
     /// Returns the intersection of this element and another
     fn intersection(&self, other: &Self) -> Self {
+        let name = self.name.intersection(&other.name);
         let version = self.version.intersection(&other.version);
         let build_number = self.build_number.intersection(&other.build_number);
-        if version == Range::none() || build_number == Range::none() {
+        let build = self.build.intersection(&other.build);
+        if name == NameConstraint::none()
+            || version == Range::none()
+            || build_number == Range::none()
+            || build == BuildStringSet::none()
+        {
             Self::none()
         } else {
             Self {
+                name,
                 version,
                 build_number,
+                build,
             }
         }
     }
 
     /// Returns true if the specified packages matches this instance
     pub fn contains(&self, package: &PackageRecord) -> bool {
-        self.version.contains(&package.version) && self.build_number.contains(&package.build_number)
+        self.name.contains(&package.name)
+            && self.version.contains(&package.version)
+            && self.build_number.contains(&package.build_number)
+            && self.build.contains(&package.build)
+    }
+
+    /// Returns true if `self ∩ other` comes out structurally equal to `self`, which
+    /// implies `self ⊆ other`. The check is one-sided: a real subset whose intersection
+    /// with `other` takes a different structural form (e.g. a build string pinned by the
+    /// positive glob `py37h0` against an `other` that only excludes `py38h0`) is
+    /// reported as not a subset.
+    fn is_subset(&self, other: &Self) -> bool {
+        &self.intersection(other) == self
+    }
+}
+
+impl Display for MatchSpecElement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} build_number {}",
+            self.name, self.version, self.build_number
+        )?;
+        if self.build != BuildStringSet::any() {
+            write!(f, " build_string {}", self.build)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MatchSpecElement {
+    type Err = ParseMatchSpecConstraintsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (head, tail) = s.split_once(" build_number ").ok_or_else(|| {
+            ParseMatchSpecConstraintsError(format!("missing 'build_number' in {s:?}"))
+        })?;
+        let (name, version) = head
+            .split_once(' ')
+            .ok_or_else(|| ParseMatchSpecConstraintsError(format!("missing version in {s:?}")))?;
+        let (build_number, build) = match tail.split_once(" build_string ") {
+            Some((build_number, build)) => (build_number, Some(build)),
+            None => (tail, None),
+        };
+
+        Ok(Self {
+            name: name.parse()?,
+            version: version
+                .parse()
+                .map_err(|e| format!("invalid version range {version:?}: {e}"))?,
+            build_number: build_number
+                .parse()
+                .map_err(|e| format!("invalid build number range {build_number:?}: {e}"))?,
+            build: build.map(str::parse).transpose()?.unwrap_or_else(BuildStringSet::any),
+        })
     }
 }
 
+/// Reduces a candidate list of DNF groups via subsumption elimination (absorption):
+/// whenever `a.is_subset(b)`, `a` contributes nothing to the union and is dropped. A
+/// group contained in another only in a way `is_subset` does not detect is kept, so the
+/// result is not always minimal. Short-circuits to a single `any()` group the instant one surfaces, since
+/// `any() ∪ x == any()` for any `x`. The result is sorted on a content-derived key so
+/// that two group lists built from the same elements in different orders compare equal.
+fn minimize_groups(groups: impl IntoIterator<Item = MatchSpecElement>) -> Vec<MatchSpecElement> {
+    let mut minimal: Vec<MatchSpecElement> = Vec::new();
+    for group in groups {
+        if group == MatchSpecElement::none() {
+            continue;
+        }
+        if group == MatchSpecElement::any() {
+            return vec![MatchSpecElement::any()];
+        }
+        if minimal.iter().any(|existing| group.is_subset(existing)) {
+            continue;
+        }
+        minimal.retain(|existing| !existing.is_subset(&group));
+        minimal.push(group);
+    }
+
+    minimal.sort_by_cached_key(|group| format!("{group:?}"));
+    minimal
+}
+
 /// Represents several constraints as a DNF.
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct MatchSpecConstraints {
@@ -62,12 +525,18 @@ impl From<MatchSpec> for MatchSpecConstraints {
     fn from(spec: MatchSpec) -> Self {
         Self {
             groups: vec![MatchSpecElement {
+                name: NameConstraint::single_positive(spec.name.clone()),
                 version: spec.version.map(Into::into).unwrap_or_else(|| Range::any()),
                 build_number: spec
                     .build_number
                     .clone()
                     .map(Range::equal)
                     .unwrap_or_else(|| Range::any()),
+                build: spec
+                    .build
+                    .clone()
+                    .map(|build| BuildStringSet::single_positive(BuildGlob::new(build)))
+                    .unwrap_or_else(BuildStringSet::any),
             }],
         }
     }
@@ -81,7 +550,30 @@ impl From<MatchSpecElement> for MatchSpecConstraints {
 
 impl Display for MatchSpecConstraints {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "bla")
+        if self.groups.is_empty() {
+            // `empty()` has no groups to join, so fall back to the same "matches
+            // nothing" sentinel `NameConstraint`/`Range` already use elsewhere.
+            write!(f, "!")
+        } else {
+            write!(f, "{}", self.groups.iter().join(" | "))
+        }
+    }
+}
+
+impl FromStr for MatchSpecConstraints {
+    type Err = ParseMatchSpecConstraintsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "!" {
+            return Ok(Self { groups: vec![] });
+        }
+        let groups = s
+            .split(" | ")
+            .map(MatchSpecElement::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            groups: minimize_groups(groups),
+        })
     }
 }
 
@@ -95,8 +587,10 @@ impl VersionSet for MatchSpecConstraints {
     fn full() -> Self {
         Self {
             groups: vec![MatchSpecElement {
+                name: NameConstraint::any(),
                 version: Range::any(),
                 build_number: Range::any(),
+                build: BuildStringSet::any(),
             }],
         }
     }
@@ -104,8 +598,10 @@ impl VersionSet for MatchSpecConstraints {
     fn singleton(v: Self::V) -> Self {
         Self {
             groups: vec![MatchSpecElement {
+                name: NameConstraint::single_positive(v.name.clone()),
                 version: Range::equal(v.version),
                 build_number: Range::equal(v.build_number),
+                build: BuildStringSet::single_positive(BuildGlob::new(v.build)),
             }],
         }
     }
@@ -118,52 +614,66 @@ impl VersionSet for MatchSpecConstraints {
         } else {
             let mut permutations = Vec::with_capacity(self.groups.len());
             for spec in self.groups.iter() {
-                let mut group_entries: SmallVec<[MatchSpecElement; 2]> = SmallVec::new();
+                let mut group_entries: SmallVec<[MatchSpecElement; 4]> = SmallVec::new();
+                for name_complement in spec.name.negated_terms() {
+                    group_entries.push(MatchSpecElement {
+                        name: name_complement,
+                        version: Range::any(),
+                        build_number: Range::any(),
+                        build: BuildStringSet::any(),
+                    });
+                }
+
                 let version_complement = spec.version.negate();
                 if version_complement != Range::none() {
                     group_entries.push(MatchSpecElement {
+                        name: NameConstraint::any(),
                         version: version_complement,
                         build_number: Range::any(),
+                        build: BuildStringSet::any(),
+                    });
+                }
+
+                let build_number_complement = spec.build_number.negate();
+                if build_number_complement != Range::none() {
+                    group_entries.push(MatchSpecElement {
+                        name: NameConstraint::any(),
+                        version: Range::any(),
+                        build_number: build_number_complement,
+                        build: BuildStringSet::any(),
                     });
                 }
 
-                let build_complement = spec.build_number.negate();
-                if build_complement != Range::none() {
+                for build_complement in spec.build.negated_terms() {
                     group_entries.push(MatchSpecElement {
+                        name: NameConstraint::any(),
                         version: Range::any(),
-                        build_number: spec.build_number.negate(),
+                        build_number: Range::any(),
+                        build: build_complement,
                     });
                 }
 
                 permutations.push(group_entries);
             }
 
-            let mut groups = HashSet::new();
+            let mut candidates = Vec::new();
             for perm in permutations.into_iter().multi_cartesian_product() {
                 let group = perm.into_iter().reduce(|a, b| a.intersection(&b)).unwrap();
 
                 if group == MatchSpecElement::any() {
                     return MatchSpecConstraints::from(group);
-                } else if group != MatchSpecElement::none() {
-                    groups.insert(group);
                 }
+                candidates.push(group);
             }
 
             Self {
-                groups: groups
-                    .into_iter()
-                    .sorted_by_cached_key(|e| {
-                        let mut hasher = DefaultHasher::new();
-                        e.hash(&mut hasher);
-                        hasher.finish()
-                    })
-                    .collect(),
+                groups: minimize_groups(candidates),
             }
         }
     }
 
     fn intersection(&self, other: &Self) -> Self {
-        let mut groups = once(self.groups.iter())
+        let candidates = once(self.groups.iter())
             .chain(once(other.groups.iter()))
             .multi_cartesian_product()
             .map(|elems| {
@@ -173,20 +683,15 @@ impl VersionSet for MatchSpecConstraints {
                     .reduce(|a, b| a.intersection(&b))
                     .unwrap()
             })
-            .filter(|group| group != &MatchSpecElement::none())
             .collect_vec();
 
-        if groups.iter().any(|group| group == &MatchSpecElement::any()) {
+        if candidates.iter().any(|group| group == &MatchSpecElement::any()) {
             return MatchSpecElement::any().into();
         }
 
-        groups.sort_by_cached_key(|e| {
-            let mut hasher = DefaultHasher::new();
-            e.hash(&mut hasher);
-            hasher.finish()
-        });
-
-        Self { groups }
+        Self {
+            groups: minimize_groups(candidates),
+        }
     }
 
     fn contains(&self, v: &Self::V) -> bool {
@@ -194,6 +699,54 @@ impl VersionSet for MatchSpecConstraints {
     }
 }
 
+/// Whether `preferred_order` yields candidates from highest to lowest version (the
+/// default, used during normal resolution) or lowest to highest (useful for
+/// minimal-version resolution).
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum VersionOrdering {
+    #[default]
+    HighestFirst,
+    LowestFirst,
+}
+
+/// Orders candidate packages the way the solver should try them, borrowing cargo's
+/// `VersionPreferences` idea: filters `candidates` down to the ones `constraints`
+/// allows, then sorts by version (`ordering` controls the direction), then by
+/// descending build number, and among records equal on both puts any record with
+/// non-empty `track_features` after the others -- conda's convention for discouraging
+/// feature-carrying builds. `track_features` is only this last tie-breaker: a
+/// feature-carrying record still comes before every record of a less preferred version
+/// or build number. `contains` alone can't express this decision order, only membership.
+pub fn preferred_order<'r>(
+    constraints: &MatchSpecConstraints,
+    candidates: impl IntoIterator<Item = &'r PackageRecord>,
+    ordering: VersionOrdering,
+) -> Vec<&'r PackageRecord> {
+    let mut matches: Vec<&PackageRecord> = candidates
+        .into_iter()
+        .filter(|record| constraints.contains(record))
+        .collect();
+
+    matches.sort_by(|a, b| {
+        let by_version = match ordering {
+            VersionOrdering::HighestFirst => b.version.cmp(&a.version),
+            VersionOrdering::LowestFirst => a.version.cmp(&b.version),
+        };
+        by_version
+            .then_with(|| b.build_number.cmp(&a.build_number))
+            .then_with(|| has_track_features(a).cmp(&has_track_features(b)))
+    });
+
+    matches
+}
+
+fn has_track_features(record: &PackageRecord) -> bool {
+    record
+        .track_features
+        .as_ref()
+        .is_some_and(|features| !features.is_empty())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::match_spec_constraints::MatchSpecConstraints;
@@ -258,4 +811,282 @@ mod tests {
             constraint.complement().union(&constraint)
         );
     }
+
+    #[test]
+    fn glob_match_wildcards() {
+        use super::glob_match;
+
+        assert!(glob_match(b"py37*", b"py37h0"));
+        assert!(!glob_match(b"py37*", b"py38h0"));
+        assert!(glob_match(b"*cuda*", b"linux_cuda11"));
+        assert!(!glob_match(b"*cuda*", b"linux_cpu"));
+    }
+
+    #[test]
+    fn disjoint_build_globs_intersect_to_empty() {
+        use super::{BuildGlob, BuildStringSet, MatchSpecElement, NameConstraint};
+        use crate::Range;
+
+        fn glob_constraint(build: &str) -> MatchSpecConstraints {
+            MatchSpecElement {
+                name: NameConstraint::single_positive("numpy".to_string()),
+                version: Range::any(),
+                build_number: Range::any(),
+                build: BuildStringSet::single_positive(BuildGlob::new(build)),
+            }
+            .into()
+        }
+
+        let py37 = glob_constraint("py37*");
+        let py38 = glob_constraint("py38*");
+
+        assert_eq!(py37.intersection(&py38), MatchSpecConstraints::empty());
+
+        let record = PackageRecord {
+            name: "numpy".to_string(),
+            version: Version::from_str("1.0.0").unwrap(),
+            build: "py37h0".to_string(),
+            build_number: 0,
+            subdir: "".to_string(),
+            md5: None,
+            sha256: None,
+            arch: None,
+            platform: None,
+            depends: vec![],
+            constrains: vec![],
+            track_features: None,
+            features: None,
+            preferred_env: None,
+            license: None,
+            license_family: None,
+            timestamp: None,
+            date: None,
+            size: None,
+        };
+        assert!(py37.contains(&record));
+        assert!(!py38.contains(&record));
+    }
+
+    #[test]
+    fn display_parse_round_trip() {
+        let record = PackageRecord {
+            name: "numpy".to_string(),
+            version: Version::from_str("1.2.3").unwrap(),
+            build: "py37h0".to_string(),
+            build_number: 1,
+            subdir: "".to_string(),
+            md5: None,
+            sha256: None,
+            arch: None,
+            platform: None,
+            depends: vec![],
+            constrains: vec![],
+            track_features: None,
+            features: None,
+            preferred_env: None,
+            license: None,
+            license_family: None,
+            timestamp: None,
+            date: None,
+            size: None,
+        };
+
+        let constraint = MatchSpecConstraints::singleton(record).complement();
+        let parsed = MatchSpecConstraints::from_str(&constraint.to_string()).unwrap();
+
+        assert_eq!(parsed, constraint);
+    }
+
+    #[test]
+    fn display_parse_round_trip_empty_and_full() {
+        let empty = MatchSpecConstraints::empty();
+        assert_eq!(
+            MatchSpecConstraints::from_str(&empty.to_string()).unwrap(),
+            empty
+        );
+
+        let full = MatchSpecConstraints::full();
+        assert_eq!(
+            MatchSpecConstraints::from_str(&full.to_string()).unwrap(),
+            full
+        );
+    }
+
+    fn package(version: &str, build_number: usize, track_features: Option<Vec<String>>) -> PackageRecord {
+        PackageRecord {
+            name: "numpy".to_string(),
+            version: Version::from_str(version).unwrap(),
+            build: "".to_string(),
+            build_number,
+            subdir: "".to_string(),
+            md5: None,
+            sha256: None,
+            arch: None,
+            platform: None,
+            depends: vec![],
+            constrains: vec![],
+            track_features,
+            features: None,
+            preferred_env: None,
+            license: None,
+            license_family: None,
+            timestamp: None,
+            date: None,
+            size: None,
+        }
+    }
+
+    #[test]
+    fn preferred_order_favors_higher_builds_and_avoids_track_features() {
+        use crate::match_spec_constraints::{preferred_order, VersionOrdering};
+
+        let low = package("1.0.0", 0, None);
+        let high_build = package("1.0.0", 1, None);
+        let high_version = package("2.0.0", 0, None);
+        let feature_carrying = package("2.0.0", 0, Some(vec!["mkl".to_string()]));
+        let candidates = [&low, &high_build, &high_version, &feature_carrying];
+
+        let constraint = MatchSpecConstraints::full();
+        let ordered = preferred_order(&constraint, candidates, VersionOrdering::HighestFirst);
+
+        assert_eq!(
+            ordered,
+            vec![&high_version, &feature_carrying, &high_build, &low]
+        );
+
+        let ordered_lowest_first =
+            preferred_order(&constraint, candidates, VersionOrdering::LowestFirst);
+        assert_eq!(ordered_lowest_first[0], &high_build);
+    }
+}
+
+/// Cross-validates the `VersionSet` algebra against a brute-force oracle, in the spirit
+/// of cargo's resolver-tests SAT checks: enumerate a small finite grid of packages,
+/// compute each constraint's membership bitset by brute force, and assert that
+/// `complement`/`intersection`/`union` agree with the corresponding bitwise operations
+/// on those oracle bitsets.
+#[cfg(test)]
+mod proptests {
+    use super::MatchSpecConstraints;
+    use crate::{PackageRecord, Version};
+    use pubgrub::version_set::VersionSet;
+    use proptest::prelude::*;
+    use std::str::FromStr;
+
+    const NAMES: [&str; 2] = ["numpy", "scipy"];
+    const VERSIONS: [&str; 2] = ["1", "2"];
+    const BUILD_NUMBERS: [usize; 2] = [0, 1];
+    const BUILDS: [&str; 2] = ["py37h0", "py38h0"];
+
+    /// The grid of packages the oracle enumerates: every (name, version, build_number,
+    /// build) combination, so the name (chunk0-2) and build-string (chunk0-1) dimensions
+    /// are exercised just as much as version and build_number are.
+    fn grid() -> Vec<PackageRecord> {
+        NAMES
+            .iter()
+            .flat_map(|name| {
+                VERSIONS.iter().flat_map(move |version| {
+                    BUILD_NUMBERS.iter().flat_map(move |&build_number| {
+                        BUILDS.iter().map(move |build| PackageRecord {
+                            name: name.to_string(),
+                            version: Version::from_str(version).unwrap(),
+                            build: build.to_string(),
+                            build_number,
+                            subdir: "".to_string(),
+                            md5: None,
+                            sha256: None,
+                            arch: None,
+                            platform: None,
+                            depends: vec![],
+                            constrains: vec![],
+                            track_features: None,
+                            features: None,
+                            preferred_env: None,
+                            license: None,
+                            license_family: None,
+                            timestamp: None,
+                            date: None,
+                            size: None,
+                        })
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// The oracle: a constraint's "true" membership bitset over the grid, computed by
+    /// brute-force `contains` checks rather than by evaluating the DNF algebraically.
+    fn bitset(constraint: &MatchSpecConstraints, grid: &[PackageRecord]) -> Vec<bool> {
+        grid.iter().map(|record| constraint.contains(record)).collect()
+    }
+
+    /// Picks one grid package as a `singleton` constraint.
+    fn leaf() -> impl Strategy<Value = MatchSpecConstraints> {
+        (0..grid().len()).prop_map(|i| MatchSpecConstraints::singleton(grid()[i].clone()))
+    }
+
+    /// Builds an arbitrary constraint out of singletons combined with `complement`,
+    /// `intersection` and `union`, mirroring what the solver actually constructs.
+    fn constraint() -> impl Strategy<Value = MatchSpecConstraints> {
+        leaf().prop_recursive(3, 8, 2, |inner| {
+            prop_oneof![
+                inner.clone().prop_map(|c| c.complement()),
+                (inner.clone(), inner.clone()).prop_map(|(a, b)| a.intersection(&b)),
+                (inner.clone(), inner).prop_map(|(a, b)| a.union(&b)),
+            ]
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(24))]
+
+        #[test]
+        fn complement_matches_oracle(c in constraint()) {
+            let grid = grid();
+            let expected: Vec<bool> = bitset(&c, &grid).into_iter().map(|b| !b).collect();
+            prop_assert_eq!(bitset(&c.complement(), &grid), expected);
+        }
+
+        #[test]
+        fn intersection_matches_oracle(a in constraint(), b in constraint()) {
+            let grid = grid();
+            let expected: Vec<bool> = bitset(&a, &grid)
+                .into_iter()
+                .zip(bitset(&b, &grid))
+                .map(|(x, y)| x && y)
+                .collect();
+            prop_assert_eq!(bitset(&a.intersection(&b), &grid), expected);
+        }
+
+        #[test]
+        fn union_matches_oracle(a in constraint(), b in constraint()) {
+            let grid = grid();
+            let expected: Vec<bool> = bitset(&a, &grid)
+                .into_iter()
+                .zip(bitset(&b, &grid))
+                .map(|(x, y)| x || y)
+                .collect();
+            prop_assert_eq!(bitset(&a.union(&b), &grid), expected);
+        }
+
+        #[test]
+        fn complement_is_involution(c in constraint()) {
+            prop_assert_eq!(c.complement().complement(), c);
+        }
+
+        #[test]
+        fn intersection_with_complement_is_empty(c in constraint()) {
+            prop_assert_eq!(c.intersection(&c.complement()), MatchSpecConstraints::empty());
+        }
+
+        #[test]
+        fn union_with_complement_is_full(c in constraint()) {
+            prop_assert_eq!(c.union(&c.complement()), MatchSpecConstraints::full());
+        }
+
+        #[test]
+        fn intersection_is_idempotent(c in constraint()) {
+            prop_assert_eq!(c.intersection(&c), c);
+        }
+    }
 }